@@ -0,0 +1,444 @@
+//! The public rule/transform vocabulary used to describe structures.
+
+use std::fmt::Debug;
+use std::fs::File;
+use std::io;
+use std::path::Path;
+use std::rc::Rc;
+
+use crate::error::Result;
+use crate::import;
+use crate::mesh::{self, Material, Mesh};
+use crate::rng::{self, Rng};
+
+/// A row-major affine 4x4 matrix.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Matrix4(pub [[f32; 4]; 4]);
+
+impl Matrix4 {
+    pub fn identity() -> Self {
+        Matrix4([
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    pub fn translation(x: f32, y: f32, z: f32) -> Self {
+        let mut m = Self::identity();
+        m.0[0][3] = x;
+        m.0[1][3] = y;
+        m.0[2][3] = z;
+        m
+    }
+
+    pub fn scale(x: f32, y: f32, z: f32) -> Self {
+        let mut m = Self::identity();
+        m.0[0][0] = x;
+        m.0[1][1] = y;
+        m.0[2][2] = z;
+        m
+    }
+
+    /// Composes `self` with `other` such that applying the result to a point is
+    /// equivalent to applying `self` first and `other` second.
+    pub fn then(&self, other: &Matrix4) -> Matrix4 {
+        let mut out = [[0.0; 4]; 4];
+        for (i, row) in out.iter_mut().enumerate() {
+            for (j, cell) in row.iter_mut().enumerate() {
+                *cell = (0..4).map(|k| other.0[i][k] * self.0[k][j]).sum();
+            }
+        }
+        Matrix4(out)
+    }
+
+    pub fn transform_point(&self, p: [f32; 3]) -> [f32; 3] {
+        let v = [p[0], p[1], p[2], 1.0];
+        let mut out = [0.0; 4];
+        for (i, row) in self.0.iter().enumerate() {
+            out[i] = (0..4).map(|k| row[k] * v[k]).sum();
+        }
+        [out[0], out[1], out[2]]
+    }
+}
+
+/// Types that can lazily produce a [`Rule`], most commonly used to defer randomness to
+/// expansion time (see the "Randomness" example in the crate docs).
+///
+/// `rng` is an independent, deterministic stream derived from this node's position in the
+/// rule graph and the seed passed to [`expand_seeded`]/[`crate::write_meshes_seeded`] (or a
+/// fixed default seed for [`expand`]/[`walk`]/[`crate::write_rule`]), so the same rule graph and
+/// seed always expand to the same structure.
+pub trait ToRule: Debug {
+    fn to_rule(&self, rng: &mut Rng) -> Rule;
+}
+
+#[derive(Debug, Clone)]
+enum RuleData {
+    Nonterminal(Vec<Rule>),
+    Terminal(Mesh),
+    Dynamic(Rc<dyn ToRule>),
+    /// `n` copies of `rule`, each offset from the last by `step`, without pre-cloning `rule`
+    /// into an `n`-long `Vec` the way [`Rule::push`]ing `n` clones would. See [`Replicate`].
+    Repeat { rule: Rc<Rule>, n: usize, step: Matrix4 },
+}
+
+/// A node in the production graph: either a leaf mesh, a dynamically-deferred rule, or a
+/// collection of subrules, each carrying its own transform and (optionally) material.
+#[derive(Debug, Clone)]
+pub struct Rule {
+    data: RuleData,
+    transform: Matrix4,
+    material: Option<Material>,
+}
+
+impl Rule {
+    /// An empty nonterminal rule; build it up with [`Rule::push`].
+    pub fn new() -> Self {
+        Rule {
+            data: RuleData::Nonterminal(Vec::new()),
+            transform: Matrix4::identity(),
+            material: None,
+        }
+    }
+
+    pub(crate) fn terminal(mesh: Mesh) -> Self {
+        Rule {
+            data: RuleData::Terminal(mesh),
+            transform: Matrix4::identity(),
+            material: None,
+        }
+    }
+
+    pub(crate) fn repeat(rule: Rule, n: usize, step: Matrix4) -> Self {
+        Rule {
+            data: RuleData::Repeat {
+                rule: Rc::new(rule),
+                n,
+                step,
+            },
+            transform: Matrix4::identity(),
+            material: None,
+        }
+    }
+
+    /// Adds `child` as a subrule.
+    pub fn push(mut self, child: Rule) -> Self {
+        if let RuleData::Nonterminal(children) = &mut self.data {
+            children.push(child);
+            return self;
+        }
+        Rule::new().push(self).push(child)
+    }
+
+    /// Applies `transform` to this rule, composed after whatever transform it already
+    /// carries, so chained `.tf()` calls nest outward.
+    pub fn tf(self, transform: impl Transform) -> Self {
+        transform.apply(self)
+    }
+
+    /// Assigns a material to this rule, inherited by every descendant that doesn't
+    /// assign its own.
+    pub fn material(mut self, material: Material) -> Self {
+        self.material = Some(material);
+        self
+    }
+
+    pub(crate) fn with_transform(mut self, m: Matrix4) -> Self {
+        self.transform = self.transform.then(&m);
+        self
+    }
+}
+
+impl Default for Rule {
+    fn default() -> Self {
+        Rule::new()
+    }
+}
+
+impl<T: ToRule + 'static> From<T> for Rule {
+    fn from(t: T) -> Self {
+        Rule {
+            data: RuleData::Dynamic(Rc::new(t)),
+            transform: Matrix4::identity(),
+            material: None,
+        }
+    }
+}
+
+/// Something that can be applied to a [`Rule`] via [`Rule::tf`].
+pub trait Transform: Debug {
+    /// The matrix this transform applies, for transforms that are a single affine map.
+    fn matrix(&self) -> Matrix4 {
+        Matrix4::identity()
+    }
+
+    /// Applies this transform to `rule`. The default wraps `rule` with [`Transform::matrix`];
+    /// transforms that expand into multiple subrules (like [`Replicate`]) override this.
+    fn apply(&self, rule: Rule) -> Rule {
+        rule.with_transform(self.matrix())
+    }
+}
+
+/// Translates by a fixed offset.
+#[derive(Debug, Clone, Copy)]
+pub struct Translate {
+    x: f32,
+    y: f32,
+    z: f32,
+}
+
+impl Translate {
+    pub fn x(x: f32) -> Self {
+        Translate { x, y: 0.0, z: 0.0 }
+    }
+
+    pub fn y(y: f32) -> Self {
+        Translate { x: 0.0, y, z: 0.0 }
+    }
+
+    pub fn z(z: f32) -> Self {
+        Translate { x: 0.0, y: 0.0, z }
+    }
+
+    pub fn by(x: f32, y: f32, z: f32) -> Self {
+        Translate { x, y, z }
+    }
+}
+
+impl Transform for Translate {
+    fn matrix(&self) -> Matrix4 {
+        Matrix4::translation(self.x, self.y, self.z)
+    }
+}
+
+/// Scales by a fixed factor per axis.
+#[derive(Debug, Clone, Copy)]
+pub struct Scale {
+    x: f32,
+    y: f32,
+    z: f32,
+}
+
+impl Scale {
+    pub fn uniform(s: f32) -> Self {
+        Scale { x: s, y: s, z: s }
+    }
+
+    pub fn by(x: f32, y: f32, z: f32) -> Self {
+        Scale { x, y, z }
+    }
+}
+
+impl Transform for Scale {
+    fn matrix(&self) -> Matrix4 {
+        Matrix4::scale(self.x, self.y, self.z)
+    }
+}
+
+/// Repeats a rule `n` times, applying `transform` cumulatively between copies.
+#[derive(Debug)]
+pub struct Replicate {
+    n: usize,
+    transform: Box<dyn Transform>,
+}
+
+impl Replicate {
+    pub fn n(n: usize, transform: impl Transform + 'static) -> Self {
+        Replicate {
+            n,
+            transform: Box::new(transform),
+        }
+    }
+}
+
+impl Transform for Replicate {
+    fn apply(&self, rule: Rule) -> Rule {
+        Rule::repeat(rule, self.n, self.transform.matrix())
+    }
+}
+
+/// A unit cube centered on the origin.
+pub fn cube() -> Rule {
+    Rule::terminal(mesh::cube())
+}
+
+/// A box centered on the origin with independent half-extents per axis.
+pub fn cuboid(half_x: f32, half_y: f32, half_z: f32) -> Rule {
+    Rule::terminal(mesh::cuboid(half_x, half_y, half_z))
+}
+
+/// A UV-sphere of radius `0.5` centered on the origin, refined by `subdivisions`.
+pub fn sphere(subdivisions: u32) -> Rule {
+    Rule::terminal(mesh::sphere(subdivisions))
+}
+
+/// A capped cylinder of radius `0.5` and height `1.0` centered on the origin, with
+/// `segments` quads around its circumference; values below `3` are clamped up.
+pub fn cylinder(segments: u32) -> Rule {
+    Rule::terminal(mesh::cylinder(segments))
+}
+
+/// An open tube (a cylinder's side wall with no end caps).
+pub fn tube(segments: u32) -> Rule {
+    Rule::terminal(mesh::tube(segments))
+}
+
+/// Loads a Wavefront OBJ file at `path` as a single mesh rule, placeable with `Translate`,
+/// `Scale`, `Replicate`, etc. exactly like `cube()`.
+pub fn mesh_from_obj(path: impl AsRef<Path>) -> Result<Rule> {
+    load_obj(File::open(path)?)
+}
+
+/// Like [`mesh_from_obj`], but reads the OBJ from an already-open reader.
+pub fn load_obj(reader: impl io::Read) -> Result<Rule> {
+    Ok(Rule::terminal(import::load_obj(reader)?))
+}
+
+/// A node's position in the rule graph, used to derive its RNG stream: the world seed plus
+/// a running discriminator built up from the child (or copy) index taken at each
+/// `Nonterminal` or `Repeat` step.
+#[derive(Debug, Clone, Copy)]
+struct EvalContext {
+    seed: u64,
+    path: u64,
+}
+
+impl EvalContext {
+    fn root(seed: u64) -> Self {
+        EvalContext { seed, path: 0 }
+    }
+
+    fn child(&self, index: usize) -> Self {
+        EvalContext {
+            seed: self.seed,
+            path: rng::combine_path(self.path, index),
+        }
+    }
+
+    fn rng(&self) -> Rng {
+        rng::node_rng(self.seed, self.path)
+    }
+}
+
+fn walk_rule(
+    rule: &Rule,
+    transform: Matrix4,
+    material: Material,
+    ctx: EvalContext,
+    visit: &mut dyn FnMut(Mesh),
+) {
+    let transform = rule.transform.then(&transform);
+    let material = rule.material.unwrap_or(material);
+    match &rule.data {
+        RuleData::Terminal(mesh) => {
+            let mut mesh = mesh.transformed(transform);
+            mesh.material = material;
+            visit(mesh);
+        }
+        RuleData::Nonterminal(children) => {
+            for (index, child) in children.iter().enumerate() {
+                walk_rule(child, transform, material, ctx.child(index), visit);
+            }
+        }
+        RuleData::Dynamic(rule_fn) => {
+            let mut node_rng = ctx.rng();
+            let sub_rule = rule_fn.to_rule(&mut node_rng);
+            walk_rule(&sub_rule, transform, material, ctx, visit);
+        }
+        RuleData::Repeat { rule, n, step } => {
+            let mut acc = Matrix4::identity();
+            for index in 0..*n {
+                walk_rule(rule, acc.then(&transform), material, ctx.child(index), visit);
+                acc = acc.then(step);
+            }
+        }
+    }
+}
+
+/// Expands `rule`, calling `visit` with each world-space mesh it describes as soon as it's
+/// produced, rather than materializing the whole expansion first. A deep recursive rule (or
+/// one with a large `Replicate` count) can be streamed straight to an exporter this way,
+/// keeping peak memory proportional to a single mesh instead of the whole structure. Uses a
+/// fixed default seed for any `ToRule` it encounters; see [`walk_seeded`] for reproducible
+/// control over randomized rules.
+pub fn walk(rule: &Rule, visit: impl FnMut(Mesh)) {
+    walk_seeded(rule, 0, visit)
+}
+
+/// Like [`walk`], but every `ToRule::to_rule` call receives an RNG derived solely from `seed`
+/// and that node's position in the graph, so the same `seed` and rule graph always visit
+/// byte-identical meshes in the same order.
+pub fn walk_seeded(rule: &Rule, seed: u64, mut visit: impl FnMut(Mesh)) {
+    walk_rule(
+        rule,
+        Matrix4::identity(),
+        Material::default(),
+        EvalContext::root(seed),
+        &mut visit,
+    );
+}
+
+/// Expands a rule graph into the flat list of world-space meshes it describes, using a
+/// fixed default seed for any `ToRule` it encounters. Use [`expand_seeded`] for reproducible
+/// control over randomized rules, or [`walk`]/[`walk_seeded`] to avoid materializing the
+/// whole list at once.
+pub fn expand(rule: Rule) -> Vec<Mesh> {
+    expand_seeded(0, rule)
+}
+
+/// Expands a rule graph into the flat list of world-space meshes it describes. The same
+/// `seed` and rule graph always produce byte-identical output: every `ToRule::to_rule` call
+/// receives an RNG derived solely from `seed` and that node's position in the graph.
+pub fn expand_seeded(seed: u64, rule: Rule) -> Vec<Mesh> {
+    let mut meshes = Vec::new();
+    walk_seeded(&rule, seed, |mesh| meshes.push(mesh));
+    meshes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn material_is_inherited_unless_overridden_by_a_descendant() {
+        let red = Material::new([1.0, 0.0, 0.0, 1.0], 0.0, 0.5, [0.0, 0.0, 0.0]);
+        let blue = Material::new([0.0, 0.0, 1.0, 1.0], 0.0, 0.5, [0.0, 0.0, 0.0]);
+
+        let rule = Rule::new()
+            .push(cube())
+            .push(cube().material(blue))
+            .material(red);
+
+        let meshes = expand(rule);
+        assert_eq!(meshes[0].material, red);
+        assert_eq!(meshes[1].material, blue);
+    }
+
+    #[derive(Debug)]
+    struct RandomMaterial;
+
+    impl ToRule for RandomMaterial {
+        fn to_rule(&self, rng: &mut Rng) -> Rule {
+            let value = rng.next_f32();
+            cube().material(Material::new([value, 0.0, 0.0, 1.0], 0.0, 0.5, [0.0, 0.0, 0.0]))
+        }
+    }
+
+    #[test]
+    fn expand_seeded_is_reproducible_for_the_same_seed_and_rule_graph() {
+        let rule = || Rule::from(RandomMaterial).push(Rule::from(RandomMaterial));
+        let colors = |meshes: Vec<Mesh>| meshes.iter().map(|m| m.material.base_color[0]).collect::<Vec<_>>();
+
+        assert_eq!(colors(expand_seeded(42, rule())), colors(expand_seeded(42, rule())));
+    }
+
+    #[test]
+    fn sibling_dynamic_rules_draw_independent_random_values() {
+        let rule = Rule::from(RandomMaterial).push(Rule::from(RandomMaterial));
+        let meshes = expand_seeded(7, rule);
+        assert_eq!(meshes.len(), 2);
+        assert_ne!(meshes[0].material.base_color[0], meshes[1].material.base_color[0]);
+    }
+}
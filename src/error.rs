@@ -0,0 +1,38 @@
+use std::fmt;
+use std::io;
+
+/// Errors that can occur while expanding a rule graph or writing it out.
+#[derive(Debug)]
+pub enum Error {
+    /// Propagated from the sink passed to [`crate::write_meshes`], or from reading a file
+    /// handed to [`crate::mesh_from_obj`].
+    Io(io::Error),
+    /// A malformed record encountered while parsing an imported OBJ file.
+    Parse(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "io error: {}", e),
+            Error::Parse(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            Error::Parse(_) => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
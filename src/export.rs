@@ -0,0 +1,141 @@
+use std::io::Write;
+
+use crate::error::Result;
+use crate::mesh::{self, Material, NormalMode};
+
+/// Adds `material` to `materials` if it isn't already present, assigning it the next
+/// `material_N` name. Lets a palette be built up one mesh at a time, without holding every
+/// mesh in memory at once. Returns whether `material` was newly added, so a streaming caller
+/// knows when it needs to emit a matching `.mtl` entry.
+pub fn dedupe_material(materials: &mut Vec<(String, Material)>, material: Material) -> bool {
+    if materials.iter().any(|(_, m)| *m == material) {
+        return false;
+    }
+    let name = format!("material_{}", materials.len());
+    materials.push((name, material));
+    true
+}
+
+/// Deduplicates the materials used across `meshes` into a stable, order-preserving
+/// `(name, material)` list, so a scene with a handful of repeated colors produces a
+/// compact palette instead of one `.mtl` entry per mesh.
+pub fn collect_materials(meshes: &[mesh::Mesh]) -> Vec<(String, Material)> {
+    let mut materials = Vec::new();
+    for mesh in meshes {
+        dedupe_material(&mut materials, mesh.material);
+    }
+    materials
+}
+
+/// Writes a `.mtl` file describing `materials`.
+pub fn render_mtl(materials: &[(String, Material)], mut sink: impl Write) -> Result<()> {
+    for (name, material) in materials {
+        render_mtl_entry(name, material, &mut sink)?;
+    }
+    Ok(())
+}
+
+/// Writes a single `newmtl` entry, the piece [`render_mtl`] repeats per material. Exposed on
+/// its own so a streaming writer can emit each entry the first time it meets that material,
+/// instead of waiting to know the whole palette up front.
+pub fn render_mtl_entry(name: &str, material: &Material, mut sink: impl Write) -> Result<()> {
+    writeln!(sink, "newmtl {}", name)?;
+    writeln!(
+        sink,
+        "Kd {} {} {}",
+        material.base_color[0], material.base_color[1], material.base_color[2]
+    )?;
+    writeln!(sink, "d {}", material.base_color[3])?;
+    writeln!(sink, "Pm {}", material.metallic)?;
+    writeln!(sink, "Pr {}", material.roughness)?;
+    writeln!(
+        sink,
+        "Ke {} {} {}",
+        material.emissive[0], material.emissive[1], material.emissive[2]
+    )?;
+    Ok(())
+}
+
+/// Writes `mesh` as `v`/`vn`/`f` records, offsetting vertex and normal indices by
+/// `vertex_offset`/`normal_offset` so multiple meshes can be concatenated into one OBJ, and
+/// emits a `usemtl` statement referencing its entry in `materials`. `mode` selects flat
+/// (one normal per face) or smooth (averaged per-vertex) shading. Returns the number of
+/// normals written, so the caller can advance its normal offset for the next mesh.
+pub fn render_obj(
+    mesh: &mesh::Mesh,
+    vertex_offset: usize,
+    normal_offset: usize,
+    mode: NormalMode,
+    materials: &[(String, Material)],
+    mut sink: impl Write,
+) -> Result<usize> {
+    for v in &mesh.vertices {
+        writeln!(sink, "v {} {} {}", v[0], v[1], v[2])?;
+    }
+
+    let normals = mesh.normals(mode);
+    for n in &normals {
+        writeln!(sink, "vn {} {} {}", n[0], n[1], n[2])?;
+    }
+
+    if let Some((name, _)) = materials.iter().find(|(_, m)| *m == mesh.material) {
+        writeln!(sink, "usemtl {}", name)?;
+    }
+
+    let normal_indices = mesh.normal_indices(mode);
+    for (face, face_normals) in mesh.faces.iter().zip(&normal_indices) {
+        write!(sink, "f")?;
+        for (&vertex, &normal) in face.iter().zip(face_normals) {
+            write!(
+                sink,
+                " {}//{}",
+                vertex + vertex_offset + 1,
+                normal + normal_offset + 1
+            )?;
+        }
+        writeln!(sink)?;
+    }
+    Ok(normals.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dedupe_material_assigns_stable_sequential_names_and_skips_duplicates() {
+        let mut materials = Vec::new();
+        let red = Material::new([1.0, 0.0, 0.0, 1.0], 0.0, 0.5, [0.0, 0.0, 0.0]);
+        let blue = Material::new([0.0, 0.0, 1.0, 1.0], 0.0, 0.5, [0.0, 0.0, 0.0]);
+
+        assert!(dedupe_material(&mut materials, red));
+        assert!(dedupe_material(&mut materials, blue));
+        assert!(!dedupe_material(&mut materials, red));
+
+        assert_eq!(
+            materials.iter().map(|(name, _)| name.as_str()).collect::<Vec<_>>(),
+            vec!["material_0", "material_1"]
+        );
+    }
+
+    #[test]
+    fn render_mtl_entry_writes_the_expected_fields() {
+        let material = Material::new([0.2, 0.6, 0.9, 0.5], 0.3, 0.8, [1.0, 0.0, 0.0]);
+        let mut sink = Vec::new();
+        render_mtl_entry("material_0", &material, &mut sink).unwrap();
+        let text = String::from_utf8(sink).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+
+        assert_eq!(
+            lines,
+            vec![
+                "newmtl material_0",
+                "Kd 0.2 0.6 0.9",
+                "d 0.5",
+                "Pm 0.3",
+                "Pr 0.8",
+                "Ke 1 0 0",
+            ]
+        );
+    }
+}
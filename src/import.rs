@@ -0,0 +1,148 @@
+//! Parses Wavefront OBJ files into [`crate::mesh::Mesh`], so user-authored models can be
+//! used as rule primitives just like the builtins.
+
+use std::io::{self, BufRead};
+
+use crate::error::{Error, Result};
+use crate::mesh::Mesh;
+
+/// Parses a Wavefront OBJ from `reader` into a single mesh, triangulating any face with
+/// more than three vertices via a fan from its first vertex.
+///
+/// `vt`/`vn` records and any directive this crate doesn't care about (`mtllib`, `usemtl`,
+/// `g`, `o`, `s`, comments, blank lines, ...) are ignored; normals are instead (re)computed
+/// on export via [`crate::NormalMode`].
+pub fn load_obj(reader: impl io::Read) -> Result<Mesh> {
+    let reader = io::BufReader::new(reader);
+    let mut vertices = Vec::new();
+    let mut faces = Vec::new();
+
+    for (line_number, line) in reader.lines().enumerate() {
+        let line = line?;
+        let mut tokens = line.split_whitespace();
+        match tokens.next() {
+            Some("v") => vertices.push(parse_vertex(&mut tokens, line_number, &line)?),
+            Some("f") => {
+                let indices = tokens
+                    .map(|token| face_vertex_index(token, vertices.len(), line_number, &line))
+                    .collect::<Result<Vec<_>>>()?;
+                if indices.len() < 3 {
+                    return Err(parse_error(line_number, &line));
+                }
+                for i in 1..indices.len() - 1 {
+                    faces.push(vec![indices[0], indices[i], indices[i + 1]]);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(Mesh::new(vertices, faces))
+}
+
+fn parse_vertex<'a>(
+    tokens: &mut impl Iterator<Item = &'a str>,
+    line_number: usize,
+    line: &str,
+) -> Result<[f32; 3]> {
+    let coords = tokens
+        .map(|token| token.parse::<f32>().map_err(|_| parse_error(line_number, line)))
+        .collect::<Result<Vec<_>>>()?;
+    match coords[..] {
+        [x, y, z] => Ok([x, y, z]),
+        _ => Err(parse_error(line_number, line)),
+    }
+}
+
+/// Parses the vertex index out of an `f` record token (`"12"`, `"12/3"`, `"12//4"`, or
+/// `"12/3/4"`), resolving OBJ's 1-based (and optionally negative/relative) indices to a
+/// 0-based index into the vertices parsed so far.
+fn face_vertex_index(token: &str, vertex_count: usize, line_number: usize, line: &str) -> Result<usize> {
+    let vertex_token = token.split('/').next().unwrap_or(token);
+    let index: isize = vertex_token.parse().map_err(|_| parse_error(line_number, line))?;
+    let index = match index {
+        i if i > 0 => i as usize - 1,
+        i if i < 0 => vertex_count
+            .checked_sub((-i) as usize)
+            .ok_or_else(|| parse_error(line_number, line))?,
+        _ => return Err(parse_error(line_number, line)),
+    };
+    if index >= vertex_count {
+        return Err(parse_error(line_number, line));
+    }
+    Ok(index)
+}
+
+fn parse_error(line_number: usize, line: &str) -> Error {
+    Error::Parse(format!("invalid OBJ record at line {}: {:?}", line_number + 1, line))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_vertices_and_triangulates_quads() {
+        let obj = "\
+# a flat unit square in the y = 0 plane
+v 0 0 0
+v 1 0 0
+v 1 0 1
+v 0 0 1
+f 1 2 3 4
+";
+        let mesh = load_obj(obj.as_bytes()).unwrap();
+        assert_eq!(mesh.vertices.len(), 4);
+        assert_eq!(mesh.vertices[1], [1.0, 0.0, 0.0]);
+        assert_eq!(mesh.faces, vec![vec![0, 1, 2], vec![0, 2, 3]]);
+    }
+
+    #[test]
+    fn ignores_vt_vn_and_other_directives() {
+        let obj = "\
+mtllib scene.mtl
+o Square
+v 0 0 0
+vt 0 0
+v 1 0 0
+vn 0 1 0
+v 1 0 1
+usemtl material_0
+f 1/1/1 2/2/1 3/3/1
+s 1
+";
+        let mesh = load_obj(obj.as_bytes()).unwrap();
+        assert_eq!(mesh.vertices.len(), 3);
+        assert_eq!(mesh.faces, vec![vec![0, 1, 2]]);
+    }
+
+    #[test]
+    fn supports_negative_relative_face_indices() {
+        let obj = "\
+v 0 0 0
+v 1 0 0
+v 1 0 1
+f -3 -2 -1
+";
+        let mesh = load_obj(obj.as_bytes()).unwrap();
+        assert_eq!(mesh.faces, vec![vec![0, 1, 2]]);
+    }
+
+    #[test]
+    fn surfaces_malformed_records_as_parse_errors() {
+        let obj = "v 0 0\n";
+        match load_obj(obj.as_bytes()) {
+            Err(Error::Parse(_)) => {}
+            other => panic!("expected a parse error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn surfaces_out_of_range_face_index_as_parse_error() {
+        let obj = "v 0 0 0\nv 1 0 0\nv 1 0 1\nf 1 2 9\n";
+        match load_obj(obj.as_bytes()) {
+            Err(Error::Parse(_)) => {}
+            other => panic!("expected a parse error, got {:?}", other),
+        }
+    }
+}
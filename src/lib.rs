@@ -7,18 +7,21 @@
 //! them to describe structures. For example:
 //!
 //! ````
-//! cube()
+//! # use immense::*;
+//! cube();
 //! ````
 //! ![](https://i.imgur.com/s68Kk0U.png)
 //!
 //! ````
-//! cube().tf(Translate::x(3))
+//! # use immense::*;
+//! cube().tf(Translate::x(3.0));
 //! ````
 //!
 //! ![](https://i.imgur.com/1nALK9q.png)
 //!
 //! ````
-//! cube().tf(Replicate::n(3, Translate::y(1.1)))
+//! # use immense::*;
+//! cube().tf(Replicate::n(3, Translate::y(1.1)));
 //! ````
 //!
 //! ![](https://i.imgur.com/xqufPmN.png)
@@ -27,7 +30,9 @@
 //!
 //! Recursive structures are particularly convenient to represent.
 //!
-//! ```
+//! ```ignore
+//! use immense::*;
+//!
 //! fn recursive_tile(depth_budget: usize) -> Rule {
 //!    let rule = Rule::new()
 //!        .push(cube().tf(Translate::by(0.25, 0.25, 0.0)).tf(Scale::by(0.4)))
@@ -59,20 +64,36 @@
 //!
 //! ![](https://i.imgur.com/huqVLHE.png)
 //!
+//! # Materials
+//!
+//! Rules can carry a material, the way Bevy's `StandardMaterial` describes a PBR surface
+//! (base color, metallic, roughness, emissive). A material applies to every mesh under the
+//! rule that doesn't set its own, so a recursive structure can be colored by depth with one
+//! `.material(...)` call per level. `write_meshes` writes a companion `.mtl` file with a
+//! deduplicated palette and the matching `mtllib`/`usemtl` statements in the OBJ.
+//!
+//! ````
+//! # use immense::*;
+//! cube().material(Material::new([0.2, 0.6, 0.9, 1.0], 0.0, 0.8, [0.0, 0.0, 0.0]));
+//! ````
+//!
 //! # Randomness
 //!
 //! Rules can be constructed with randomness. To do this construction of the rule must be delayed
 //! to mesh generation so the random values are different for each invocation, since the number of
 //! invocations is not known until then. For needs like this we can make Rules and Subrules from
-//! any type implementing ```ToRule```.
+//! any type implementing ```ToRule```. The `Rng` each `to_rule` call receives is seeded
+//! deterministically from its position in the rule graph, so `write_meshes_seeded` with the
+//! same seed always regenerates byte-identical output.
+//!
+//! ````ignore
+//! use immense::*;
 //!
-//! ````
 //! #[derive(Debug)] struct RandCube;
 //!
-//! impl ToRule for RandCube {fn to_rule(&self) -> Rule {cube().tf(*thread_rng()
+//! impl ToRule for RandCube {fn to_rule(&self, rng: &mut Rng) -> Rule {cube().tf(*rng
 //!    .choose(&[Translate::x(0.1), Translate::x(-0.1), Translate::x(0.2), Translate::x(-0.2),
-//!            ])
-//!            .unwrap())
+//!            ]))
 //!    }
 //!}
 //!
@@ -82,29 +103,169 @@
 //!````
 //!
 //! ![](https://i.imgur.com/bSNc6jw.png)
-
-#![feature(custom_attribute)]
-#![feature(bind_by_move_pattern_guards)]
-#![feature(stmt_expr_attributes)]
-#![feature(const_fn)]
+//!
+//! # Importing meshes
+//!
+//! A user-authored model can be used as a rule primitive exactly like `cube()`, loaded from a
+//! Wavefront OBJ file.
+//!
+//! ````no_run
+//! # use immense::*;
+//! # fn main() -> Result<(), immense::Error> {
+//! mesh_from_obj("chair.obj")?.tf(Scale::uniform(0.5));
+//! # Ok(())
+//! # }
+//! ````
 
 mod api;
 mod error;
 mod export;
+mod import;
 mod mesh;
+mod rng;
 
 pub use crate::api::*;
 pub use crate::error::Error;
+pub use crate::mesh::{Material, NormalMode};
+pub use crate::rng::Rng;
 
 use crate::error::Result;
 use std::io;
 
-pub fn write_meshes(meshes: Vec<mesh::Mesh>, mut sink: impl io::Write) -> Result<()> {
+/// Writes `meshes` as a Wavefront OBJ to `obj_sink`, plus a companion `.mtl` material
+/// library to `mtl_sink` referenced via `mtllib <mtllib_name>`.
+///
+/// Identical materials are deduplicated into a compact palette (`material_0`, `material_1`, ...)
+/// regardless of how many meshes use them. `normal_mode` selects faceted (`Flat`, one normal
+/// per face) or smooth (`Smooth`, averaged per-vertex) shading for the `vn`/`f v//vn` records.
+pub fn write_meshes(
+    meshes: Vec<mesh::Mesh>,
+    mtllib_name: &str,
+    normal_mode: NormalMode,
+    mut obj_sink: impl io::Write,
+    mtl_sink: impl io::Write,
+) -> Result<()> {
+    let materials = export::collect_materials(&meshes);
+    writeln!(obj_sink, "mtllib {}", mtllib_name)?;
+    export::render_mtl(&materials, mtl_sink)?;
+
     let mut vertex_offset = 0;
+    let mut normal_offset = 0;
     for mesh in meshes {
         let vertex_count = mesh.vertices.len();
-        export::render_obj(mesh, vertex_offset, &mut sink)?;
+        let normal_count = export::render_obj(
+            &mesh,
+            vertex_offset,
+            normal_offset,
+            normal_mode,
+            &materials,
+            &mut obj_sink,
+        )?;
         vertex_offset += vertex_count;
+        normal_offset += normal_count;
     }
     Ok(())
 }
+
+/// Like [`write_meshes`], but expands `rule` itself so every `ToRule::to_rule` call along
+/// the way receives a deterministic RNG derived from `seed`. The same `seed` and rule graph
+/// always produce byte-identical OBJ output.
+pub fn write_meshes_seeded(
+    seed: u64,
+    rule: api::Rule,
+    mtllib_name: &str,
+    normal_mode: NormalMode,
+    obj_sink: impl io::Write,
+    mtl_sink: impl io::Write,
+) -> Result<()> {
+    let meshes = api::expand_seeded(seed, rule);
+    write_meshes(meshes, mtllib_name, normal_mode, obj_sink, mtl_sink)
+}
+
+/// Expands and writes `rule` incrementally, rendering and dropping each mesh as it's
+/// produced instead of collecting the whole expansion into a `Vec` first (as
+/// [`write_meshes`] requires). Peak memory stays proportional to a single mesh, which
+/// matters for a deep recursive rule or a large `Replicate` count. Walks `rule` once,
+/// growing the material palette and writing each mesh's `.mtl` entry the first time that
+/// material is seen, rather than expanding the whole tree a second time to collect it up
+/// front.
+pub fn write_rule(
+    rule: api::Rule,
+    mtllib_name: &str,
+    normal_mode: NormalMode,
+    obj_sink: impl io::Write,
+    mtl_sink: impl io::Write,
+) -> Result<()> {
+    write_rule_seeded(0, rule, mtllib_name, normal_mode, obj_sink, mtl_sink)
+}
+
+/// Like [`write_rule`], but every `ToRule::to_rule` call receives a deterministic RNG
+/// derived from `seed`. The same `seed` and rule graph always produce byte-identical OBJ
+/// output.
+pub fn write_rule_seeded(
+    seed: u64,
+    rule: api::Rule,
+    mtllib_name: &str,
+    normal_mode: NormalMode,
+    mut obj_sink: impl io::Write,
+    mut mtl_sink: impl io::Write,
+) -> Result<()> {
+    writeln!(obj_sink, "mtllib {}", mtllib_name)?;
+
+    let mut materials: Vec<(String, mesh::Material)> = Vec::new();
+    let mut vertex_offset = 0;
+    let mut normal_offset = 0;
+    let mut write_result = Ok(());
+    api::walk_seeded(&rule, seed, |mesh| {
+        if write_result.is_err() {
+            return;
+        }
+        if export::dedupe_material(&mut materials, mesh.material) {
+            let (name, material) = materials.last().unwrap();
+            write_result = export::render_mtl_entry(name, material, &mut mtl_sink);
+            if write_result.is_err() {
+                return;
+            }
+        }
+        let vertex_count = mesh.vertices.len();
+        write_result = export::render_obj(
+            &mesh,
+            vertex_offset,
+            normal_offset,
+            normal_mode,
+            &materials,
+            &mut obj_sink,
+        )
+        .map(|normal_count| {
+            vertex_offset += vertex_count;
+            normal_offset += normal_count;
+        });
+    });
+    write_result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::{cube, sphere, Replicate, Translate};
+
+    #[test]
+    fn write_rule_matches_write_meshes_for_the_same_rule_and_seed() {
+        let rule = || {
+            cube()
+                .tf(Replicate::n(3, Translate::x(1.0)))
+                .push(sphere(2))
+        };
+
+        let mut batch_obj = Vec::new();
+        let mut batch_mtl = Vec::new();
+        write_meshes_seeded(7, rule(), "scene.mtl", NormalMode::Flat, &mut batch_obj, &mut batch_mtl).unwrap();
+
+        let mut streamed_obj = Vec::new();
+        let mut streamed_mtl = Vec::new();
+        write_rule_seeded(7, rule(), "scene.mtl", NormalMode::Flat, &mut streamed_obj, &mut streamed_mtl).unwrap();
+
+        assert_eq!(batch_obj, streamed_obj);
+        assert_eq!(batch_mtl, streamed_mtl);
+    }
+}
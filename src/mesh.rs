@@ -0,0 +1,428 @@
+use crate::api::Matrix4;
+
+/// A PBR-ish surface description, modeled after Bevy's `StandardMaterial`.
+///
+/// Two materials with identical fields are considered the same material for export
+/// purposes, so structures that reuse a handful of colors end up with a compact `.mtl`
+/// palette instead of one entry per mesh.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Material {
+    pub base_color: [f32; 4],
+    pub metallic: f32,
+    pub roughness: f32,
+    pub emissive: [f32; 3],
+}
+
+impl Material {
+    pub fn new(base_color: [f32; 4], metallic: f32, roughness: f32, emissive: [f32; 3]) -> Self {
+        Material {
+            base_color,
+            metallic,
+            roughness,
+            emissive,
+        }
+    }
+}
+
+impl Default for Material {
+    fn default() -> Self {
+        Material {
+            base_color: [0.8, 0.8, 0.8, 1.0],
+            metallic: 0.0,
+            roughness: 0.5,
+            emissive: [0.0, 0.0, 0.0],
+        }
+    }
+}
+
+/// A triangulated or quadrangulated piece of geometry with a single material.
+///
+/// `faces` indexes into `vertices`; each face is a CCW polygon (the exporter is free to
+/// emit it as-is, since Wavefront OBJ supports n-gon faces).
+#[derive(Debug, Clone)]
+pub struct Mesh {
+    pub vertices: Vec<[f32; 3]>,
+    pub faces: Vec<Vec<usize>>,
+    pub material: Material,
+}
+
+impl Mesh {
+    pub fn new(vertices: Vec<[f32; 3]>, faces: Vec<Vec<usize>>) -> Self {
+        Mesh {
+            vertices,
+            faces,
+            material: Material::default(),
+        }
+    }
+
+    /// Returns a copy of this mesh with `transform` applied to every vertex.
+    pub(crate) fn transformed(&self, transform: Matrix4) -> Mesh {
+        Mesh {
+            vertices: self
+                .vertices
+                .iter()
+                .map(|v| transform.transform_point(*v))
+                .collect(),
+            faces: self.faces.clone(),
+            material: self.material,
+        }
+    }
+
+    /// The geometric normal of `face`, as the normalized cross product of two of its edge
+    /// vectors. Assumes `face` is planar and CCW-wound as seen from outside.
+    fn face_normal(&self, face: &[usize]) -> [f32; 3] {
+        let v0 = self.vertices[face[0]];
+        let v1 = self.vertices[face[1]];
+        let v2 = self.vertices[face[2]];
+        normalize(cross(sub(v1, v0), sub(v2, v0)))
+    }
+
+    /// The normal for each face (flat) or vertex (smooth), per `mode`.
+    pub(crate) fn normals(&self, mode: NormalMode) -> Vec<[f32; 3]> {
+        match mode {
+            NormalMode::Flat => self.faces.iter().map(|face| self.face_normal(face)).collect(),
+            NormalMode::Smooth => {
+                let mut accumulated = vec![[0.0; 3]; self.vertices.len()];
+                for face in &self.faces {
+                    let normal = self.face_normal(face);
+                    for &vertex in face {
+                        accumulated[vertex] = add(accumulated[vertex], normal);
+                    }
+                }
+                accumulated.into_iter().map(normalize).collect()
+            }
+        }
+    }
+
+    /// For each face, the index into [`Mesh::normals`]' result to use per vertex of that
+    /// face: the face's own index (flat, one normal shared by the whole face) or the
+    /// vertex's own index (smooth, one normal per vertex).
+    pub(crate) fn normal_indices(&self, mode: NormalMode) -> Vec<Vec<usize>> {
+        match mode {
+            NormalMode::Flat => (0..self.faces.len())
+                .map(|i| vec![i; self.faces[i].len()])
+                .collect(),
+            NormalMode::Smooth => self.faces.clone(),
+        }
+    }
+}
+
+/// Whether normals are shared per-face (faceted shading) or averaged per-vertex (smooth
+/// shading) when exporting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NormalMode {
+    Flat,
+    Smooth,
+}
+
+fn sub(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn add(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[0] + b[0], a[1] + b[1], a[2] + b[2]]
+}
+
+fn cross(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn normalize(v: [f32; 3]) -> [f32; 3] {
+    let len = (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt();
+    if len == 0.0 {
+        v
+    } else {
+        [v[0] / len, v[1] / len, v[2] / len]
+    }
+}
+
+/// A unit cube centered on the origin, with quad faces wound CCW as seen from outside.
+pub fn cube() -> Mesh {
+    cuboid(0.5, 0.5, 0.5)
+}
+
+/// A box centered on the origin with independent half-extents per axis, with quad faces
+/// wound CCW as seen from outside.
+pub fn cuboid(half_x: f32, half_y: f32, half_z: f32) -> Mesh {
+    let (hx, hy, hz) = (half_x, half_y, half_z);
+    let vertices = vec![
+        [-hx, -hy, -hz],
+        [hx, -hy, -hz],
+        [hx, hy, -hz],
+        [-hx, hy, -hz],
+        [-hx, -hy, hz],
+        [hx, -hy, hz],
+        [hx, hy, hz],
+        [-hx, hy, hz],
+    ];
+    let faces = vec![
+        vec![0, 3, 2, 1], // back  (-z)
+        vec![4, 5, 6, 7], // front (+z)
+        vec![0, 4, 7, 3], // left  (-x)
+        vec![1, 2, 6, 5], // right (+x)
+        vec![0, 1, 5, 4], // bottom (-y)
+        vec![3, 7, 6, 2], // top    (+y)
+    ];
+    Mesh::new(vertices, faces)
+}
+
+/// A UV-sphere of radius `0.5` centered on the origin. `subdivisions` controls both the
+/// number of latitude bands and (doubled) longitude sectors; values below `2` are clamped up.
+pub fn sphere(subdivisions: u32) -> Mesh {
+    use std::f32::consts::{FRAC_PI_2, PI};
+
+    let stacks = subdivisions.max(2);
+    let sectors = subdivisions.max(2) * 2;
+    let radius = 0.5;
+
+    let mut vertices = vec![[0.0, -radius, 0.0]]; // south pole, index 0
+    for i in 1..stacks {
+        let phi = PI * (i as f32 / stacks as f32) - FRAC_PI_2;
+        let y = radius * phi.sin();
+        let ring_radius = radius * phi.cos();
+        for j in 0..sectors {
+            let theta = 2.0 * PI * (j as f32 / sectors as f32);
+            vertices.push([ring_radius * theta.cos(), y, ring_radius * theta.sin()]);
+        }
+    }
+    let north_pole = vertices.len();
+    vertices.push([0.0, radius, 0.0]);
+
+    let ring_start = |i: u32| 1 + (i - 1) * sectors;
+
+    let mut faces = Vec::new();
+    for j in 0..sectors {
+        let a = ring_start(1) + j;
+        let b = ring_start(1) + (j + 1) % sectors;
+        faces.push(vec![a as usize, b as usize, 0]);
+    }
+    for i in 1..stacks - 1 {
+        let r0 = ring_start(i);
+        let r1 = ring_start(i + 1);
+        for j in 0..sectors {
+            let a = r0 + j;
+            let b = r0 + (j + 1) % sectors;
+            let c = r1 + (j + 1) % sectors;
+            let d = r1 + j;
+            faces.push(vec![d as usize, c as usize, b as usize, a as usize]);
+        }
+    }
+    for j in 0..sectors {
+        let a = ring_start(stacks - 1) + j;
+        let b = ring_start(stacks - 1) + (j + 1) % sectors;
+        faces.push(vec![north_pole, b as usize, a as usize]);
+    }
+
+    Mesh::new(vertices, faces)
+}
+
+/// A capped cylinder of radius `0.5` and height `1.0` centered on the origin, with
+/// `segments` quads around its circumference; values below `3` are clamped up.
+pub fn cylinder(segments: u32) -> Mesh {
+    tube_or_cylinder(segments, true)
+}
+
+/// An open tube: a cylinder's side wall with no end caps.
+pub fn tube(segments: u32) -> Mesh {
+    tube_or_cylinder(segments, false)
+}
+
+fn tube_or_cylinder(segments: u32, capped: bool) -> Mesh {
+    use std::f32::consts::PI;
+
+    let segments = segments.max(3);
+    let radius = 0.5;
+    let half_height = 0.5;
+
+    let ring = |y: f32| {
+        (0..segments)
+            .map(|i| {
+                let theta = 2.0 * PI * (i as f32 / segments as f32);
+                [radius * theta.cos(), y, radius * theta.sin()]
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let mut vertices = ring(-half_height);
+    vertices.extend(ring(half_height));
+
+    let bottom = |i: u32| i as usize;
+    let top = |i: u32| (segments + i) as usize;
+
+    let mut faces = Vec::new();
+    for i in 0..segments {
+        let ni = (i + 1) % segments;
+        faces.push(vec![bottom(i), top(i), top(ni), bottom(ni)]);
+    }
+
+    if capped {
+        let bottom_center = vertices.len();
+        vertices.push([0.0, -half_height, 0.0]);
+        let top_center = vertices.len();
+        vertices.push([0.0, half_height, 0.0]);
+
+        for i in 0..segments {
+            let ni = (i + 1) % segments;
+            faces.push(vec![bottom_center, bottom(i), bottom(ni)]);
+            faces.push(vec![top_center, top(ni), top(i)]);
+        }
+    }
+
+    Mesh::new(vertices, faces)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Six times the signed volume enclosed by a watertight mesh, computed by summing the
+    /// scalar triple product of each triangulated face against the origin. Positive iff every
+    /// face is wound CCW as seen from outside, which is what we want to check here.
+    fn six_times_signed_volume(mesh: &Mesh) -> f32 {
+        mesh.faces
+            .iter()
+            .flat_map(|face| {
+                let v0 = mesh.vertices[face[0]];
+                (1..face.len() - 1).map(move |i| (v0, mesh.vertices[face[i]], mesh.vertices[face[i + 1]]))
+            })
+            .map(|(v0, v1, v2)| {
+                let cross = [
+                    v1[1] * v2[2] - v1[2] * v2[1],
+                    v1[2] * v2[0] - v1[0] * v2[2],
+                    v1[0] * v2[1] - v1[1] * v2[0],
+                ];
+                v0[0] * cross[0] + v0[1] * cross[1] + v0[2] * cross[2]
+            })
+            .sum()
+    }
+
+    fn assert_face_indices_in_bounds(mesh: &Mesh) {
+        for face in &mesh.faces {
+            for &index in face {
+                assert!(index < mesh.vertices.len(), "face index {} out of bounds", index);
+            }
+        }
+    }
+
+    #[test]
+    fn cube_is_outward_wound_unit_box() {
+        let mesh = cube();
+        assert_eq!(mesh.vertices.len(), 8);
+        assert_eq!(mesh.faces.len(), 6);
+        assert!((six_times_signed_volume(&mesh) - 6.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn cuboid_has_independent_half_extents() {
+        let mesh = cuboid(1.0, 2.0, 3.0);
+        assert_face_indices_in_bounds(&mesh);
+        // Volume of a 2x4x6 box, times 6.
+        assert!((six_times_signed_volume(&mesh) - (6.0 * 2.0 * 4.0 * 6.0)).abs() < 1e-3);
+    }
+
+    #[test]
+    fn sphere_is_outward_wound_and_converges_to_expected_volume() {
+        let coarse = sphere(6);
+        let fine = sphere(48);
+        assert_face_indices_in_bounds(&coarse);
+        assert_face_indices_in_bounds(&fine);
+
+        let expected = 6.0 * (4.0 / 3.0) * std::f32::consts::PI * 0.5f32.powi(3);
+        let coarse_volume = six_times_signed_volume(&coarse);
+        let fine_volume = six_times_signed_volume(&fine);
+        assert!(coarse_volume > 0.0);
+        assert!((fine_volume - expected).abs() < expected * 0.05);
+        assert!((fine_volume - expected).abs() < (coarse_volume - expected).abs());
+    }
+
+    #[test]
+    fn cylinder_is_outward_wound_and_approximates_expected_volume() {
+        let mesh = cylinder(32);
+        assert_face_indices_in_bounds(&mesh);
+        let expected = 6.0 * std::f32::consts::PI * 0.5f32.powi(2) * 1.0;
+        assert!((six_times_signed_volume(&mesh) - expected).abs() < expected * 0.05);
+    }
+
+    #[test]
+    fn tube_has_no_end_caps() {
+        let mesh = tube(8);
+        assert_eq!(mesh.vertices.len(), 16);
+        assert_eq!(mesh.faces.len(), 8);
+        assert_face_indices_in_bounds(&mesh);
+    }
+
+    #[test]
+    fn write_meshes_vertex_offset_stays_correct_across_mixed_primitives() {
+        let meshes = vec![cube(), cuboid(1.0, 2.0, 3.0), sphere(4), cylinder(6), tube(6)];
+        let vertex_counts: Vec<usize> = meshes.iter().map(|m| m.vertices.len()).collect();
+        let face_counts: Vec<usize> = meshes.iter().map(|m| m.faces.len()).collect();
+
+        let mut obj = Vec::new();
+        let mut mtl = Vec::new();
+        crate::write_meshes(meshes, "scene.mtl", NormalMode::Flat, &mut obj, &mut mtl).unwrap();
+        let obj = String::from_utf8(obj).unwrap();
+
+        let face_lines: Vec<&str> = obj.lines().filter(|line| line.starts_with("f ")).collect();
+        assert_eq!(face_lines.len(), face_counts.iter().sum::<usize>());
+
+        let mut vertex_offset = 0;
+        let mut face_line = face_lines.into_iter();
+        for (mesh_index, (&vertex_count, &face_count)) in
+            vertex_counts.iter().zip(&face_counts).enumerate()
+        {
+            for _ in 0..face_count {
+                let line = face_line.next().unwrap();
+                for token in line.trim_start_matches("f ").split_whitespace() {
+                    let vertex: usize = token.split("//").next().unwrap().parse().unwrap();
+                    assert!(
+                        vertex > vertex_offset && vertex <= vertex_offset + vertex_count,
+                        "mesh {} emitted vertex index {} outside its offset range ({}, {}]",
+                        mesh_index,
+                        vertex,
+                        vertex_offset,
+                        vertex_offset + vertex_count
+                    );
+                }
+            }
+            vertex_offset += vertex_count;
+        }
+    }
+
+    #[test]
+    fn flat_normals_point_outward_and_one_per_face() {
+        let mesh = cube();
+        let normals = mesh.normals(NormalMode::Flat);
+        assert_eq!(normals.len(), mesh.faces.len());
+        for (face, normal) in mesh.faces.iter().zip(&normals) {
+            let centroid = face
+                .iter()
+                .map(|&i| mesh.vertices[i])
+                .fold([0.0, 0.0, 0.0], add);
+            let dot = normal[0] * centroid[0] + normal[1] * centroid[1] + normal[2] * centroid[2];
+            assert!(dot > 0.0, "face normal should point away from the cube's center");
+            let len = (normal[0] * normal[0] + normal[1] * normal[1] + normal[2] * normal[2]).sqrt();
+            assert!((len - 1.0).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn smooth_normals_one_per_vertex_and_unit_length() {
+        let mesh = sphere(8);
+        let normals = mesh.normals(NormalMode::Smooth);
+        assert_eq!(normals.len(), mesh.vertices.len());
+        for normal in &normals {
+            let len = (normal[0] * normal[0] + normal[1] * normal[1] + normal[2] * normal[2]).sqrt();
+            assert!((len - 1.0).abs() < 1e-4);
+        }
+        // On a sphere centered at the origin, the smoothed normal at each vertex should
+        // point in (roughly) the same direction as the vertex itself.
+        for (vertex, normal) in mesh.vertices.iter().zip(&normals) {
+            let dot = vertex[0] * normal[0] + vertex[1] * normal[1] + vertex[2] * normal[2];
+            assert!(dot > 0.0);
+        }
+    }
+}
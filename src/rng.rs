@@ -0,0 +1,115 @@
+//! Deterministic per-node randomness.
+//!
+//! Every node in a rule expansion derives its own RNG stream from a world seed plus its
+//! position in the tree (the running child-index path down from the root), following the
+//! seed-mixing approach used by plantex: the two values are hashed together with FNV-1a to
+//! produce 128 bits, which seed an xorshift128+ generator. Same seed and same rule graph
+//! always walk the same paths, so they always derive the same streams.
+
+const FNV_PRIME: u64 = 0x100000001b3;
+const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+// An arbitrary second basis, distinct from the standard one, used to derive the other 64
+// bits of the 128-bit seed from the same input bytes.
+const FNV_OFFSET_BASIS_2: u64 = 0x84222325cbf29ce4;
+
+fn fnv1a(bytes: &[u8], basis: u64) -> u64 {
+    bytes
+        .iter()
+        .fold(basis, |hash, &b| (hash ^ u64::from(b)).wrapping_mul(FNV_PRIME))
+}
+
+/// Combines a parent path discriminator with a child index into a new, still-deterministic
+/// path discriminator, so siblings never collide.
+pub(crate) fn combine_path(path: u64, index: usize) -> u64 {
+    let mut bytes = [0u8; 16];
+    bytes[..8].copy_from_slice(&path.to_le_bytes());
+    bytes[8..].copy_from_slice(&(index as u64).to_le_bytes());
+    fnv1a(&bytes, FNV_OFFSET_BASIS)
+}
+
+/// Derives the RNG for the node at `path` within a tree evaluated with `world_seed`.
+pub(crate) fn node_rng(world_seed: u64, path: u64) -> Rng {
+    let mut bytes = [0u8; 16];
+    bytes[..8].copy_from_slice(&world_seed.to_le_bytes());
+    bytes[8..].copy_from_slice(&path.to_le_bytes());
+    let hi = fnv1a(&bytes, FNV_OFFSET_BASIS);
+    let lo = fnv1a(&bytes, FNV_OFFSET_BASIS_2);
+    Rng::from_seed(hi, lo)
+}
+
+/// A small, fast, non-cryptographic xorshift128+ generator, seeded deterministically for
+/// each node of a rule expansion. Passed to [`crate::ToRule::to_rule`] so random choices
+/// made while expanding a rule are reproducible.
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: [u64; 2],
+}
+
+impl Rng {
+    pub(crate) fn from_seed(seed_hi: u64, seed_lo: u64) -> Self {
+        // xorshift128+ is undefined for an all-zero state.
+        let state = if seed_hi == 0 && seed_lo == 0 {
+            [1, 0]
+        } else {
+            [seed_hi, seed_lo]
+        };
+        Rng { state }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut s1 = self.state[0];
+        let s0 = self.state[1];
+        self.state[0] = s0;
+        s1 ^= s1 << 23;
+        s1 ^= s1 >> 17;
+        s1 ^= s0 ^ (s0 >> 26);
+        self.state[1] = s1;
+        s0.wrapping_add(s1)
+    }
+
+    /// Returns a pseudo-random value in `[0.0, 1.0)`.
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    /// Picks a uniformly random element of `items`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `items` is empty.
+    pub fn choose<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        let index = (self.next_u64() as usize) % items.len();
+        &items[index]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn node_rng_is_reproducible_for_the_same_seed_and_path() {
+        let sequence = |mut rng: Rng| (0..5).map(|_| rng.next_f32()).collect::<Vec<_>>();
+        assert_eq!(sequence(node_rng(42, 7)), sequence(node_rng(42, 7)));
+    }
+
+    #[test]
+    fn combine_path_diverges_across_sibling_indices() {
+        let parent = 123;
+        let paths: Vec<u64> = (0..8).map(|index| combine_path(parent, index)).collect();
+        for i in 0..paths.len() {
+            for j in (i + 1)..paths.len() {
+                assert_ne!(paths[i], paths[j], "sibling indices {} and {} collided", i, j);
+            }
+        }
+    }
+
+    #[test]
+    fn sibling_node_rngs_do_not_share_a_stream() {
+        let sequence = |mut rng: Rng| (0..5).map(|_| rng.next_f32()).collect::<Vec<_>>();
+        let world_seed = 99;
+        let a = combine_path(0, 0);
+        let b = combine_path(0, 1);
+        assert_ne!(sequence(node_rng(world_seed, a)), sequence(node_rng(world_seed, b)));
+    }
+}